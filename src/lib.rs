@@ -27,17 +27,90 @@
 #![deny(missing_docs)]
 #![deny(missing_debug_implementations)]
 
-use embedded_graphics::{pixelcolor::Rgb888, prelude::*};
+use embedded_graphics::{
+    pixelcolor::{raw::RawU32, Rgb888},
+    prelude::*,
+};
 
 const MAGIC: &[u8] = b"qoif";
 const HEADER_LENGTH: usize = 14;
 const STREAM_END: &[u8] = &[0, 0, 0, 0, 0, 0, 0, 1];
 
+/// QOI_OP_RUN chunks can encode runs of up to 62 pixels.
+const MAX_RUN_LENGTH: u8 = 62;
+
+/// A 32-bit RGBA color.
+///
+/// `embedded_graphics` has no RGBA color type of its own, so `tinyqoi` defines this one for use
+/// with [`encode`] and the alpha-preserving decode path ([`Qoi::pixels_rgba`]).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Rgba {
+    r: u8,
+    g: u8,
+    b: u8,
+    a: u8,
+}
+
+impl Rgba {
+    /// Creates a new color from its red, green, blue and alpha components.
+    pub const fn new(r: u8, g: u8, b: u8, a: u8) -> Self {
+        Self { r, g, b, a }
+    }
+
+    /// Returns the red component.
+    pub const fn r(&self) -> u8 {
+        self.r
+    }
+
+    /// Returns the green component.
+    pub const fn g(&self) -> u8 {
+        self.g
+    }
+
+    /// Returns the blue component.
+    pub const fn b(&self) -> u8 {
+        self.b
+    }
+
+    /// Returns the alpha component.
+    pub const fn a(&self) -> u8 {
+        self.a
+    }
+}
+
+impl PixelColor for Rgba {
+    type Raw = RawU32;
+}
+
+/// Number of color channels a QOI image was encoded with.
+///
+/// This is informational only: the decoder always yields fully decoded RGBA pixels
+/// regardless of this value. It can be used to decide whether an image's alpha channel is
+/// meaningful.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Channels {
+    /// Three channels: red, green and blue.
+    Rgb = 3,
+    /// Four channels: red, green, blue and alpha.
+    Rgba = 4,
+}
+
+/// Color space a QOI image was encoded in, as defined by the QOI specification.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ColorSpace {
+    /// sRGB with linear alpha.
+    Srgb = 0,
+    /// All channels are linear.
+    Linear = 1,
+}
+
 /// QOI image.
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct Qoi<'a> {
     data: &'a [u8],
     size: Size,
+    channels: Channels,
+    color_space: ColorSpace,
 }
 
 impl<'a> Qoi<'a> {
@@ -60,19 +133,88 @@ impl<'a> Qoi<'a> {
 
         let width = u32::from_be_bytes(header[0..4].try_into().unwrap());
         let height = u32::from_be_bytes(header[4..8].try_into().unwrap());
-        let _channels = header[8];
-        let _colorspace = header[9];
+
+        let channels = match header[8] {
+            4 => Channels::Rgba,
+            _ => Channels::Rgb,
+        };
+        let color_space = match header[9] {
+            1 => ColorSpace::Linear,
+            _ => ColorSpace::Srgb,
+        };
 
         Ok(Self {
             data,
             size: Size::new(width, height),
+            channels,
+            color_space,
         })
     }
 
+    /// Creates a new QOI image, additionally validating its op-stream.
+    ///
+    /// In addition to everything [`Qoi::new`] checks, this walks the whole op-stream once to
+    /// make sure every chunk is complete and that the number of decoded pixels matches
+    /// `width * height`, returning [`Error::UnexpectedEndOfStream`] or
+    /// [`Error::PixelCountMismatch`] if not. Prefer this over [`Qoi::new`] when the data comes
+    /// from an untrusted or potentially corrupt source, since [`Qoi::new`] alone doesn't
+    /// guarantee the image can be fully decoded.
+    pub fn new_checked(data: &'a [u8]) -> Result<Self, Error> {
+        let qoi = Self::new(data)?;
+        qoi.verify()?;
+        Ok(qoi)
+    }
+
+    /// Walks the whole op-stream once, checking that every chunk is complete and that the
+    /// number of decoded pixels matches `width * height`.
+    ///
+    /// See [`Qoi::new_checked`] for a constructor that does this automatically.
+    pub fn verify(&self) -> Result<(), Error> {
+        let expected = self.size.width.saturating_mul(self.size.height);
+        let mut got: u32 = 0;
+        let mut decoder = Decoder::new(self);
+
+        while let Some(_pixel) = decoder.next_pixel()? {
+            got += 1;
+        }
+
+        if got != expected {
+            return Err(Error::PixelCountMismatch { expected, got });
+        }
+
+        Ok(())
+    }
+
+    /// Returns the number of color channels this image was encoded with.
+    pub fn channels(&self) -> Channels {
+        self.channels
+    }
+
+    /// Returns the color space this image was encoded in.
+    pub fn color_space(&self) -> ColorSpace {
+        self.color_space
+    }
+
     /// Returns an iterator over this pixels in this image.
     pub fn pixels(&'a self) -> PixelsIter<'a> {
         PixelsIter::new(self)
     }
+
+    /// Returns an iterator over the pixels in this image, including their alpha channel.
+    pub fn pixels_rgba(&'a self) -> PixelsIterRgba<'a> {
+        PixelsIterRgba::new(self)
+    }
+
+    /// Wraps this image so it can be drawn onto an RGBA-capable `DrawTarget`, preserving
+    /// transparency.
+    ///
+    /// [`Qoi`] itself implements [`ImageDrawable`] with [`Rgb888`] colors and discards the
+    /// alpha channel, since most displays have no notion of transparency. Use this method to
+    /// draw sprites or icons that rely on their alpha channel, for example when compositing
+    /// onto a framebuffer.
+    pub fn rgba(&'a self) -> QoiRgba<'a> {
+        QoiRgba(self)
+    }
 }
 
 impl ImageDrawable for Qoi<'_> {
@@ -103,19 +245,51 @@ impl OriginDimensions for Qoi<'_> {
     }
 }
 
-fn hash_pixel(pixel: Rgb888, alpha: u8) -> u8 {
-    pixel
-        .r()
-        .wrapping_mul(3)
-        .wrapping_add(pixel.g().wrapping_mul(5))
-        .wrapping_add(pixel.b().wrapping_mul(7))
-        .wrapping_add(alpha.wrapping_mul(11))
+/// Wraps a [`Qoi`] image so it can be drawn with its alpha channel preserved.
+///
+/// Returned by [`Qoi::rgba()`].
+#[derive(Debug)]
+pub struct QoiRgba<'a>(&'a Qoi<'a>);
+
+impl ImageDrawable for QoiRgba<'_> {
+    type Color = Rgba;
+
+    fn draw<D>(&self, target: &mut D) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = Self::Color>,
+    {
+        target.fill_contiguous(&self.bounding_box(), self.0.pixels_rgba())
+    }
+
+    fn draw_sub_image<D>(
+        &self,
+        target: &mut D,
+        area: &embedded_graphics::primitives::Rectangle,
+    ) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = Self::Color>,
+    {
+        self.draw(&mut target.translated(-area.top_left).clipped(area))
+    }
+}
+
+impl OriginDimensions for QoiRgba<'_> {
+    fn size(&self) -> Size {
+        self.0.size
+    }
+}
+
+fn hash_pixel(r: u8, g: u8, b: u8, a: u8) -> u8 {
+    r.wrapping_mul(3)
+        .wrapping_add(g.wrapping_mul(5))
+        .wrapping_add(b.wrapping_mul(7))
+        .wrapping_add(a.wrapping_mul(11))
         % 64
 }
 
-/// Iterator over the pixels of a QOI image.
+/// Shared decoding state used by [`PixelsIter`] and [`PixelsIterRgba`].
 #[derive(Debug)]
-pub struct PixelsIter<'a> {
+struct Decoder<'a> {
     previous_color: Rgb888,
     previous_alpha: u8,
     previous_colors: [Rgb888; 64],
@@ -124,8 +298,8 @@ pub struct PixelsIter<'a> {
     run_length: u8,
 }
 
-impl<'a> PixelsIter<'a> {
-    fn new(qoi: &'a Qoi<'a>) -> Self {
+impl<'a> Decoder<'a> {
+    fn new(qoi: &Qoi<'a>) -> Self {
         Self {
             previous_color: Rgb888::BLACK,
             previous_alpha: 255,
@@ -135,18 +309,21 @@ impl<'a> PixelsIter<'a> {
             run_length: 0,
         }
     }
-}
-
-impl Iterator for PixelsIter<'_> {
-    type Item = Rgb888;
 
-    fn next(&mut self) -> Option<Self::Item> {
+    /// Decodes the next pixel, returning its color and alpha value.
+    ///
+    /// Returns `Ok(None)` once the op-stream is cleanly exhausted, and
+    /// [`Error::UnexpectedEndOfStream`] if a chunk's tag byte was read but the bytes it
+    /// requires aren't available.
+    fn next_pixel(&mut self) -> Result<Option<(Rgb888, u8)>, Error> {
         if self.run_length > 0 {
             self.run_length -= 1;
-            return Some(self.previous_color);
+            return Ok(Some((self.previous_color, self.previous_alpha)));
         }
 
-        let (byte, rest) = self.data.split_first()?;
+        let Some((byte, rest)) = self.data.split_first() else {
+            return Ok(None);
+        };
         self.data = rest;
 
         match byte {
@@ -156,7 +333,7 @@ impl Iterator for PixelsIter<'_> {
                     self.previous_color = Rgb888::new(self.data[0], self.data[1], self.data[2]);
                     self.data = &self.data[3..];
                 } else {
-                    return None;
+                    return Err(Error::UnexpectedEndOfStream);
                 }
             }
             0b11111111 => {
@@ -166,7 +343,7 @@ impl Iterator for PixelsIter<'_> {
                     self.previous_alpha = self.data[3];
                     self.data = &self.data[4..];
                 } else {
-                    return None;
+                    return Err(Error::UnexpectedEndOfStream);
                 }
             }
             _ => match byte & 0b11000000 {
@@ -175,7 +352,7 @@ impl Iterator for PixelsIter<'_> {
                     let index = usize::from(byte & 0x3F);
                     self.previous_color = self.previous_colors[index];
                     self.previous_alpha = self.previous_alphas[index];
-                    return Some(self.previous_color);
+                    return Ok(Some((self.previous_color, self.previous_alpha)));
                 }
                 0b01000000 => {
                     // QOI_OP_DIFF
@@ -191,7 +368,7 @@ impl Iterator for PixelsIter<'_> {
                 }
                 0b10000000 => {
                     // QOI_OP_LUMA
-                    if self.data.len() >= 1 {
+                    if !self.data.is_empty() {
                         let byte2 = self.data[0];
                         self.data = &self.data[1..];
 
@@ -205,21 +382,111 @@ impl Iterator for PixelsIter<'_> {
 
                         self.previous_color = Rgb888::new(r, g, b);
                     } else {
-                        return None;
+                        return Err(Error::UnexpectedEndOfStream);
                     }
                 }
-                0b11000000 | _ => {
+                _ => {
                     // QOI_OP_RUN
                     self.run_length = byte & 0x3F;
-                    return Some(self.previous_color);
+                    return Ok(Some((self.previous_color, self.previous_alpha)));
                 }
             },
         }
 
-        let index = usize::from(hash_pixel(self.previous_color, self.previous_alpha));
+        let index = usize::from(hash_pixel(
+            self.previous_color.r(),
+            self.previous_color.g(),
+            self.previous_color.b(),
+            self.previous_alpha,
+        ));
         self.previous_colors[index] = self.previous_color;
         self.previous_alphas[index] = self.previous_alpha;
-        Some(self.previous_color)
+        Ok(Some((self.previous_color, self.previous_alpha)))
+    }
+}
+
+/// Iterator over the pixels of a QOI image.
+///
+/// The alpha channel is discarded; use [`PixelsIterRgba`] to preserve it.
+#[derive(Debug)]
+pub struct PixelsIter<'a>(Decoder<'a>);
+
+impl<'a> PixelsIter<'a> {
+    fn new(qoi: &'a Qoi<'a>) -> Self {
+        Self(Decoder::new(qoi))
+    }
+
+    /// Decodes pixels into `out`, resuming from wherever the previous call left off.
+    ///
+    /// Fills `out` with as many complete `Rgb888` pixels (3 bytes each, in RGB order) as fit,
+    /// returning the number of bytes written. This lets a caller pump a small, fixed-size
+    /// buffer (e.g. sized to a DMA transfer or a display's scanline) repeatedly instead of
+    /// materializing the whole image, and partial runs correctly survive across calls. A
+    /// return value smaller than `out.len()` (rounded down to a multiple of 3) means the image
+    /// has been fully decoded.
+    pub fn decode_to_buf(&mut self, out: &mut [u8]) -> Result<usize, Error> {
+        let mut written = 0;
+
+        for chunk in out.chunks_exact_mut(3) {
+            let Some((color, _)) = self.0.next_pixel()? else {
+                break;
+            };
+
+            chunk.copy_from_slice(&[color.r(), color.g(), color.b()]);
+            written += chunk.len();
+        }
+
+        Ok(written)
+    }
+}
+
+impl Iterator for PixelsIter<'_> {
+    type Item = Rgb888;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next_pixel().ok().flatten().map(|(color, _)| color)
+    }
+}
+
+/// Iterator over the pixels of a QOI image, including the alpha channel.
+#[derive(Debug)]
+pub struct PixelsIterRgba<'a>(Decoder<'a>);
+
+impl<'a> PixelsIterRgba<'a> {
+    fn new(qoi: &'a Qoi<'a>) -> Self {
+        Self(Decoder::new(qoi))
+    }
+
+    /// Decodes pixels into `out`, resuming from wherever the previous call left off.
+    ///
+    /// Fills `out` with as many complete RGBA pixels (4 bytes each, in RGBA order) as fit,
+    /// returning the number of bytes written. See [`PixelsIter::decode_to_buf`] for the
+    /// rationale; this is the alpha-preserving counterpart.
+    pub fn decode_to_buf(&mut self, out: &mut [u8]) -> Result<usize, Error> {
+        let mut written = 0;
+
+        for chunk in out.chunks_exact_mut(4) {
+            let Some((color, alpha)) = self.0.next_pixel()? else {
+                break;
+            };
+
+            chunk.copy_from_slice(&[color.r(), color.g(), color.b(), alpha]);
+            written += chunk.len();
+        }
+
+        Ok(written)
+    }
+}
+
+impl Iterator for PixelsIterRgba<'_> {
+    type Item = Rgba;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0
+            .next_pixel()
+            .ok()
+            .flatten()
+            .map(|(color, alpha)| Rgba::new(color.r(), color.g(), color.b(), alpha))
     }
 }
 
@@ -230,6 +497,137 @@ pub enum Error {
     InvalidMagic,
     /// File is too short.
     TruncatedFile,
+    /// The output buffer passed to [`encode`] was too small to hold the encoded image.
+    OutputBufferTooSmall,
+    /// A chunk's tag byte was read but the op-stream ended before the bytes it requires.
+    ///
+    /// Returned by [`Qoi::new_checked`], [`Qoi::verify`], [`PixelsIter::decode_to_buf`] and
+    /// [`PixelsIterRgba::decode_to_buf`]; the pixel iterators' `next` silently stops decoding
+    /// instead.
+    UnexpectedEndOfStream,
+    /// The number of pixels decoded from the op-stream didn't match `width * height`.
+    ///
+    /// Only returned by [`Qoi::new_checked`] and [`Qoi::verify`].
+    PixelCountMismatch {
+        /// Number of pixels expected, i.e. `width * height`.
+        expected: u32,
+        /// Number of pixels actually decoded before the op-stream ended.
+        got: u32,
+    },
+}
+
+/// Encodes `pixels` as a QOI image into `out`, returning the number of bytes written.
+///
+/// `pixels` must yield exactly `width * height` pixels in row-major order. `out` must be
+/// large enough to hold the encoded image; if it isn't, [`Error::OutputBufferTooSmall`] is
+/// returned and the contents of `out` should be considered invalid.
+///
+/// This is the encoding counterpart to [`Qoi`] and is intended for `no_std` use, where an
+/// allocator isn't available to build up the output in a `Vec`.
+pub fn encode<I>(pixels: I, width: u32, height: u32, out: &mut [u8]) -> Result<usize, Error>
+where
+    I: IntoIterator<Item = Rgba>,
+{
+    let mut writer = Writer::new(out);
+
+    writer.write_bytes(MAGIC)?;
+    writer.write_bytes(&width.to_be_bytes())?;
+    writer.write_bytes(&height.to_be_bytes())?;
+    writer.write_bytes(&[Channels::Rgba as u8, ColorSpace::Srgb as u8])?;
+
+    let mut previous = Rgba::new(0, 0, 0, 255);
+    let mut index = [Rgba::new(0, 0, 0, 0); 64];
+    let mut run_length: u8 = 0;
+
+    for pixel in pixels {
+        if pixel == previous {
+            run_length += 1;
+
+            if run_length == MAX_RUN_LENGTH {
+                writer.write_byte(0b1100_0000 | (run_length - 1))?;
+                run_length = 0;
+            }
+
+            continue;
+        }
+
+        if run_length > 0 {
+            writer.write_byte(0b1100_0000 | (run_length - 1))?;
+            run_length = 0;
+        }
+
+        let hash = usize::from(hash_pixel(pixel.r(), pixel.g(), pixel.b(), pixel.a()));
+
+        if index[hash] == pixel {
+            writer.write_byte(hash as u8)?;
+        } else if pixel.a() == previous.a() {
+            let dr = pixel.r().wrapping_sub(previous.r()) as i8;
+            let dg = pixel.g().wrapping_sub(previous.g()) as i8;
+            let db = pixel.b().wrapping_sub(previous.b()) as i8;
+
+            if (-2..=1).contains(&dr) && (-2..=1).contains(&dg) && (-2..=1).contains(&db) {
+                let byte =
+                    0b0100_0000 | ((dr + 2) as u8) << 4 | ((dg + 2) as u8) << 2 | (db + 2) as u8;
+                writer.write_byte(byte)?;
+            } else {
+                let dr_dg = dr.wrapping_sub(dg);
+                let db_dg = db.wrapping_sub(dg);
+
+                if (-32..=31).contains(&dg)
+                    && (-8..=7).contains(&dr_dg)
+                    && (-8..=7).contains(&db_dg)
+                {
+                    writer.write_byte(0b1000_0000 | (dg + 32) as u8)?;
+                    writer.write_byte(((dr_dg + 8) as u8) << 4 | (db_dg + 8) as u8)?;
+                } else {
+                    writer.write_byte(0b1111_1110)?;
+                    writer.write_bytes(&[pixel.r(), pixel.g(), pixel.b()])?;
+                }
+            }
+        } else {
+            writer.write_byte(0b1111_1111)?;
+            writer.write_bytes(&[pixel.r(), pixel.g(), pixel.b(), pixel.a()])?;
+        }
+
+        index[hash] = pixel;
+        previous = pixel;
+    }
+
+    if run_length > 0 {
+        writer.write_byte(0b1100_0000 | (run_length - 1))?;
+    }
+
+    writer.write_bytes(STREAM_END)?;
+
+    Ok(writer.position)
+}
+
+/// Bounds-checked cursor for writing encoded bytes into a caller-supplied buffer.
+struct Writer<'a> {
+    out: &'a mut [u8],
+    position: usize,
+}
+
+impl<'a> Writer<'a> {
+    fn new(out: &'a mut [u8]) -> Self {
+        Self { out, position: 0 }
+    }
+
+    fn write_bytes(&mut self, bytes: &[u8]) -> Result<(), Error> {
+        let end = self.position + bytes.len();
+        let dest = self
+            .out
+            .get_mut(self.position..end)
+            .ok_or(Error::OutputBufferTooSmall)?;
+        dest.copy_from_slice(bytes);
+        self.position = end;
+
+        Ok(())
+    }
+
+    fn write_byte(&mut self, byte: u8) -> Result<(), Error> {
+        self.write_bytes(&[byte])
+    }
 }
 
 #[cfg(test)]
@@ -257,6 +655,98 @@ mod tests {
         assert_eq!(Qoi::new(data), Err(Error::TruncatedFile));
     }
 
+    #[test]
+    fn verify_detects_truncated_chunk() {
+        // A 1x1 image whose only chunk is a QOI_OP_RGB tag missing two of its three data bytes.
+        let data: [u8; 24] = [
+            b'q',
+            b'o',
+            b'i',
+            b'f', //
+            0,
+            0,
+            0,
+            1, // width = 1
+            0,
+            0,
+            0,
+            1, // height = 1
+            3,
+            0, // channels, colorspace
+            0b1111_1110,
+            10, // truncated QOI_OP_RGB
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+            1, // stream end
+        ];
+
+        let qoi = Qoi::new(&data).unwrap();
+        assert_eq!(qoi.verify(), Err(Error::UnexpectedEndOfStream));
+        assert_eq!(Qoi::new_checked(&data), Err(Error::UnexpectedEndOfStream));
+    }
+
+    #[test]
+    fn verify_detects_pixel_count_mismatch() {
+        // A 2x1 image whose op-stream only ever produces a single pixel.
+        let data: [u8; 26] = [
+            b'q',
+            b'o',
+            b'i',
+            b'f', //
+            0,
+            0,
+            0,
+            2, // width = 2
+            0,
+            0,
+            0,
+            1, // height = 1
+            3,
+            0, // channels, colorspace
+            0b1111_1110,
+            10,
+            20,
+            30, // one complete QOI_OP_RGB chunk
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+            1, // stream end
+        ];
+
+        let qoi = Qoi::new(&data).unwrap();
+        assert_eq!(
+            qoi.verify(),
+            Err(Error::PixelCountMismatch {
+                expected: 2,
+                got: 1
+            })
+        );
+    }
+
+    #[test]
+    fn header_metadata() {
+        let data = include_bytes!("../tests/colors.qoi");
+        let qoi = Qoi::new(data).unwrap();
+
+        assert_eq!(qoi.channels(), Channels::Rgb);
+        assert_eq!(qoi.color_space(), ColorSpace::Srgb);
+    }
+
+    #[test]
+    fn new_checked_accepts_well_formed_file() {
+        let data = include_bytes!("../tests/colors.qoi");
+        assert!(Qoi::new_checked(data).is_ok());
+    }
+
     #[test]
     fn image() {
         let data = include_bytes!("../tests/colors.qoi");
@@ -272,4 +762,252 @@ mod tests {
             "KKK", //
         ]);
     }
+
+    #[test]
+    fn image_rgba() {
+        let data = include_bytes!("../tests/colors.qoi");
+        let qoi = Qoi::new(data).unwrap();
+
+        assert!(qoi.pixels_rgba().all(|pixel| pixel.a() == 255));
+
+        let mut display = MockDisplay::<Rgba>::new();
+        Image::new(&qoi.rgba(), Point::zero())
+            .draw(&mut display)
+            .unwrap();
+
+        assert_eq!(
+            display.get_pixel(Point::zero()),
+            Some(Rgba::new(255, 0, 0, 255))
+        );
+    }
+
+    #[test]
+    fn pixels_rgba_preserves_alpha_variation() {
+        // A 2x1 image: a QOI_OP_RGBA chunk for a semi-transparent pixel, followed by a
+        // QOI_OP_INDEX chunk that hits that same (non-opaque) pixel via its hash slot. This
+        // exercises both the alpha decoded straight off the wire and the alpha carried through
+        // the `previous_alphas` index table.
+        let data: [u8; 28] = [
+            b'q',
+            b'o',
+            b'i',
+            b'f', //
+            0,
+            0,
+            0,
+            2, // width = 2
+            0,
+            0,
+            0,
+            1, // height = 1
+            4,
+            0, // channels = Rgba, colorspace = Srgb
+            0b1111_1111,
+            10,
+            20,
+            30,
+            128, // QOI_OP_RGBA: (10, 20, 30, 128)
+            20,  // QOI_OP_INDEX hitting hash_pixel(10, 20, 30, 128) == 20
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+            1, // stream end
+        ];
+
+        let qoi = Qoi::new(&data).unwrap();
+        assert_eq!(qoi.channels(), Channels::Rgba);
+
+        let pixels: [Rgba; 2] = {
+            let mut iter = qoi.pixels_rgba();
+            [iter.next().unwrap(), iter.next().unwrap()]
+        };
+        assert_eq!(pixels, [Rgba::new(10, 20, 30, 128); 2]);
+
+        let mut display = MockDisplay::<Rgba>::new();
+        Image::new(&qoi.rgba(), Point::zero())
+            .draw(&mut display)
+            .unwrap();
+
+        assert_eq!(
+            display.get_pixel(Point::zero()),
+            Some(Rgba::new(10, 20, 30, 128))
+        );
+        assert_eq!(
+            display.get_pixel(Point::new(1, 0)),
+            Some(Rgba::new(10, 20, 30, 128))
+        );
+    }
+
+    #[test]
+    fn decode_to_buf_resumes_across_calls() {
+        let data = include_bytes!("../tests/colors.qoi");
+        let qoi = Qoi::new(data).unwrap();
+
+        let mut decoded = [0u8; 27];
+        let mut offset = 0;
+        let mut pixels = qoi.pixels();
+        let mut buf = [0u8; 5];
+
+        loop {
+            let written = pixels.decode_to_buf(&mut buf).unwrap();
+            if written == 0 {
+                break;
+            }
+            decoded[offset..offset + written].copy_from_slice(&buf[..written]);
+            offset += written;
+        }
+
+        assert_eq!(offset, decoded.len());
+
+        let expected: [u8; 27] = {
+            let mut expected = [0u8; 27];
+            for (i, color) in qoi.pixels().enumerate() {
+                expected[i * 3] = color.r();
+                expected[i * 3 + 1] = color.g();
+                expected[i * 3 + 2] = color.b();
+            }
+            expected
+        };
+
+        assert_eq!(decoded, expected);
+    }
+
+    #[test]
+    fn decode_to_buf_surfaces_stream_corruption() {
+        // A 1x1 image whose only chunk is a QOI_OP_RGB tag missing two of its three data bytes,
+        // same as `verify_detects_truncated_chunk`. `decode_to_buf` must report this instead of
+        // silently treating it as a clean end of stream.
+        let data: [u8; 24] = [
+            b'q',
+            b'o',
+            b'i',
+            b'f', //
+            0,
+            0,
+            0,
+            1, // width = 1
+            0,
+            0,
+            0,
+            1, // height = 1
+            3,
+            0, // channels, colorspace
+            0b1111_1110,
+            10, // truncated QOI_OP_RGB
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+            1, // stream end
+        ];
+
+        let qoi = Qoi::new(&data).unwrap();
+
+        let mut rgb_buf = [0u8; 3];
+        assert_eq!(
+            qoi.pixels().decode_to_buf(&mut rgb_buf),
+            Err(Error::UnexpectedEndOfStream)
+        );
+
+        let mut rgba_buf = [0u8; 4];
+        assert_eq!(
+            qoi.pixels_rgba().decode_to_buf(&mut rgba_buf),
+            Err(Error::UnexpectedEndOfStream)
+        );
+    }
+
+    #[test]
+    fn encode_decode_roundtrip() {
+        let data = include_bytes!("../tests/colors.qoi");
+        let qoi = Qoi::new(data).unwrap();
+
+        let pixels = qoi
+            .pixels()
+            .map(|color| Rgba::new(color.r(), color.g(), color.b(), 255));
+
+        let mut out = [0u8; 256];
+        let len = encode(pixels, qoi.size().width, qoi.size().height, &mut out).unwrap();
+
+        let reencoded = Qoi::new(&out[..len]).unwrap();
+        assert_eq!(reencoded.size(), qoi.size());
+        assert!(reencoded.pixels().eq(qoi.pixels()));
+    }
+
+    #[test]
+    fn encode_output_buffer_too_small() {
+        let data = include_bytes!("../tests/colors.qoi");
+        let qoi = Qoi::new(data).unwrap();
+
+        let pixels = qoi
+            .pixels()
+            .map(|color| Rgba::new(color.r(), color.g(), color.b(), 255));
+
+        let mut out = [0u8; 4];
+        assert_eq!(
+            encode(pixels, qoi.size().width, qoi.size().height, &mut out),
+            Err(Error::OutputBufferTooSmall)
+        );
+    }
+
+    #[test]
+    fn encode_run_spanning_max_run_length() {
+        // 64 identical pixels: the first is encoded normally, the next 62 form a run that is
+        // flushed the instant it hits `MAX_RUN_LENGTH`, and the final pixel starts a second run
+        // that is only flushed once the pixel iterator is exhausted.
+        let pixel = Rgba::new(10, 20, 30, 255);
+        let pixels = core::iter::repeat_n(pixel, 64);
+
+        let mut out = [0u8; 64];
+        let len = encode(pixels, 64, 1, &mut out).unwrap();
+
+        let body = &out[HEADER_LENGTH..len - STREAM_END.len()];
+        assert_eq!(
+            body,
+            &[0b1111_1110, 10, 20, 30, 0b1100_0000 | 61, 0b1100_0000]
+        );
+    }
+
+    #[test]
+    fn encode_uses_luma_op() {
+        // The first pixel forces a full QOI_OP_RGB chunk so that the second pixel's delta from
+        // it is large enough to fall outside QOI_OP_DIFF but still within QOI_OP_LUMA's range.
+        let first = Rgba::new(100, 100, 100, 255);
+        let second = Rgba::new(113, 110, 107, 255);
+        let pixels = [first, second];
+
+        let mut out = [0u8; 32];
+        let len = encode(pixels, 2, 1, &mut out).unwrap();
+
+        let body = &out[HEADER_LENGTH..len - STREAM_END.len()];
+        assert_eq!(
+            body,
+            &[0b1111_1110, 100, 100, 100, 0b1000_0000 | 42, (11 << 4) | 5]
+        );
+    }
+
+    #[test]
+    fn encode_uses_index_op() {
+        // The third pixel repeats the first one's color after a different pixel in between, so
+        // it is encoded as a single QOI_OP_INDEX byte pointing back at the first pixel's slot.
+        let first = Rgba::new(100, 100, 100, 255);
+        let second = Rgba::new(5, 5, 5, 255);
+        let pixels = [first, second, first];
+
+        let mut out = [0u8; 32];
+        let len = encode(pixels, 3, 1, &mut out).unwrap();
+
+        let hash = hash_pixel(first.r(), first.g(), first.b(), first.a());
+        let body = &out[HEADER_LENGTH..len - STREAM_END.len()];
+        assert_eq!(
+            body,
+            &[0b1111_1110, 100, 100, 100, 0b1111_1110, 5, 5, 5, hash]
+        );
+    }
 }